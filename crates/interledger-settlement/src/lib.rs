@@ -16,7 +16,9 @@ mod message_service;
 
 pub use api::SettlementApi;
 pub use client::SettlementClient;
-pub use message_service::SettlementMessageService;
+pub use message_service::{
+    AddressMatchMode, SettlementErrorCode, SettlementErrorData, SettlementMessageService,
+};
 
 pub struct SettlementEngineDetails {
     /// Base URL of the settlement engine
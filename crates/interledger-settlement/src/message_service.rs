@@ -3,17 +3,103 @@ use futures::{
     future::{err, Either},
     Future, Stream,
 };
-use interledger_packet::{Address, ErrorCode, FulfillBuilder, RejectBuilder};
+use interledger_packet::{Address, ErrorCode, Fulfill, FulfillBuilder, Reject, RejectBuilder};
 use interledger_service::{BoxedIlpFuture, IncomingRequest, IncomingService};
 use reqwest::r#async::Client;
+use serde::{Deserialize, Serialize};
 use serde_json::{self, Value};
 use std::marker::PhantomData;
 
 const PEER_FULFILLMENT: [u8; 32] = [0; 32];
+/// Maximum length of the data field of ILP packets, as defined in the ILPv4 spec
+const MAX_DATA_SIZE: usize = 32767;
+
+/// Machine-readable reason that a settlement message could not be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementErrorCode {
+    NoSettlementEngine,
+    InvalidJson,
+    NotJsonObject,
+    EngineUnreachable,
+    EngineResponseUnreadable,
+    EngineResponseTooLarge,
+    EngineRejected,
+}
+
+/// Structured error included as JSON in the `data` field of the Reject packets
+/// returned by the `SettlementMessageService`, so that the peer can handle the
+/// error programmatically instead of parsing the human-readable message.
+///
+/// The descriptions are short, fixed strings so the encoded data always stays
+/// well within the ILP packet data size limit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettlementErrorData {
+    pub code: SettlementErrorCode,
+    pub description: String,
+}
+
+impl SettlementErrorData {
+    pub fn new(code: SettlementErrorCode, description: &str) -> Self {
+        SettlementErrorData {
+            code,
+            description: description.to_string(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+}
+
+/// How the destination of an incoming packet is compared with the ILP address
+/// of the account's settlement engine to decide if the packet is a settlement message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressMatchMode {
+    /// The destination must equal the settlement engine's address
+    Exact,
+    /// The settlement engine's address must be a prefix of the destination,
+    /// for example `peer.settle` matches `peer.settle.xrp-paychan`
+    Prefix,
+    /// The settlement engine's address must be a suffix of the destination,
+    /// for example `settle.xrp-paychan` matches `peer.settle.xrp-paychan`
+    Suffix,
+}
+
+impl Default for AddressMatchMode {
+    fn default() -> Self {
+        AddressMatchMode::Exact
+    }
+}
+
+impl AddressMatchMode {
+    /// Compares whole address segments so `peer.settle` does not match `peer.settlement`
+    pub fn matches(self, destination: &Address, settlement_engine_address: &Address) -> bool {
+        match self {
+            AddressMatchMode::Exact => destination == settlement_engine_address,
+            AddressMatchMode::Prefix => {
+                let mut destination_segments = destination.segments();
+                settlement_engine_address
+                    .segments()
+                    .all(|segment| destination_segments.next() == Some(segment))
+            }
+            AddressMatchMode::Suffix => {
+                let mut destination_segments = destination.segments().rev();
+                settlement_engine_address
+                    .segments()
+                    .rev()
+                    .all(|segment| destination_segments.next() == Some(segment))
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct SettlementMessageService<I, A> {
     ilp_address: Address,
+    address_match_mode: AddressMatchMode,
+    auth_token: Option<String>,
     next: I,
     http_client: Client,
     account_type: PhantomData<A>,
@@ -28,10 +114,26 @@ where
         SettlementMessageService {
             next,
             ilp_address,
+            address_match_mode: AddressMatchMode::default(),
+            auth_token: None,
             http_client: Client::new(),
             account_type: PhantomData,
         }
     }
+
+    /// Set how packet destinations are matched against the settlement engine's ILP address.
+    /// Defaults to `AddressMatchMode::Exact`.
+    pub fn address_match_mode(&mut self, address_match_mode: AddressMatchMode) -> &mut Self {
+        self.address_match_mode = address_match_mode;
+        self
+    }
+
+    /// Set the token sent as a Bearer token when forwarding messages to the settlement engine,
+    /// so that the engine can verify they came from this connector.
+    pub fn auth_token(&mut self, auth_token: String) -> &mut Self {
+        self.auth_token = Some(auth_token);
+        self
+    }
 }
 
 impl<I, A> IncomingService<A> for SettlementMessageService<I, A>
@@ -43,10 +145,14 @@ where
 
     fn handle_request(&mut self, request: IncomingRequest<A>) -> Self::Future {
         // Only handle the request if the destination address matches the ILP address
-        // of the settlement engine being used for this account
+        // of the settlement engine being used for this account. Packets that are not
+        // addressed to a settlement engine are passed on to the next service
         let ilp_address = self.ilp_address.clone();
         if let Some(settlement_engine_details) = request.from.settlement_engine_details() {
-            if request.prepare.destination() == settlement_engine_details.ilp_address {
+            if self.address_match_mode.matches(
+                &request.prepare.destination(),
+                &settlement_engine_details.ilp_address,
+            ) {
                 let ilp_address_clone = self.ilp_address.clone();
                 let mut settlement_engine_url = settlement_engine_details.url;
 
@@ -56,41 +162,43 @@ where
                             "accountId".to_string(),
                             Value::String(request.from.id().to_string()),
                         );
-                        // TODO add auth
                         settlement_engine_url
                             .path_segments_mut()
                             .expect("Invalid settlement engine URL")
                             .push("receiveMessage"); // Maybe set the idempotency flag here in the headers
-                        return Box::new(self.http_client.post(settlement_engine_url)
-                        .json(&message)
+                        let mut engine_request =
+                            self.http_client.post(settlement_engine_url).json(&message);
+                        if let Some(ref auth_token) = self.auth_token {
+                            engine_request = engine_request
+                                .header("authorization", format!("Bearer {}", auth_token));
+                        }
+                        return Box::new(engine_request
                         .send()
                         .map_err(move |error| {
                             error!("Error sending message to settlement engine: {:?}", error);
+                            let data = SettlementErrorData::new(SettlementErrorCode::EngineUnreachable, "Settlement engine unreachable").to_bytes();
                             RejectBuilder {
                                 code: ErrorCode::T00_INTERNAL_ERROR,
                                 message: b"Error sending message to settlement engine",
-                                data: &[],
+                                data: &data,
                                 triggered_by: Some(&ilp_address_clone),
                             }.build()
                         })
                         .and_then(move |response| {
                             let status = response.status();
                             if status.is_success() {
+                                let ilp_address_clone = ilp_address.clone();
                                 Either::A(response.into_body().concat2().map_err(move |err| {
                                     error!("Error concatenating settlement engine response body: {:?}", err);
+                                    let data = SettlementErrorData::new(SettlementErrorCode::EngineResponseUnreadable, "Settlement engine response unreadable").to_bytes();
                                     RejectBuilder {
                                     code: ErrorCode::T00_INTERNAL_ERROR,
                                     message: b"Error getting settlement engine response",
-                                    data: &[],
+                                    data: &data,
                                     triggered_by: Some(&ilp_address),
                                 }.build()
                                 })
-                                .and_then(|body| {
-                                    Ok(FulfillBuilder {
-                                        fulfillment: &PEER_FULFILLMENT,
-                                        data: body.as_ref(),
-                                    }.build())
-                                }))
+                                .and_then(move |body| engine_response_to_fulfill(body.as_ref(), &ilp_address_clone)))
                             } else {
                                 error!("Settlement engine rejected message with HTTP error code: {}", response.status());
                                 let code = if status.is_client_error() {
@@ -98,10 +206,11 @@ where
                                 } else {
                                     ErrorCode::T00_INTERNAL_ERROR
                                 };
+                                let data = SettlementErrorData::new(SettlementErrorCode::EngineRejected, "Settlement engine rejected message").to_bytes();
                                 Either::B(err(RejectBuilder {
                                     code,
                                     message: format!("Settlement engine rejected request with error code: {}", response.status()).as_str().as_ref(),
-                                    data: &[],
+                                    data: &data,
                                     triggered_by: Some(&ilp_address),
                                 }.build()))
                             }
@@ -113,38 +222,474 @@ where
                             request.from.id(),
                             error
                         );
+                        let data = SettlementErrorData::new(
+                            SettlementErrorCode::InvalidJson,
+                            "Message is not valid JSON",
+                        )
+                        .to_bytes();
                         return Box::new(err(RejectBuilder {
                             code: ErrorCode::F00_BAD_REQUEST,
                             message: format!("Unable to parse message as JSON: {:?}", error)
                                 .as_str()
                                 .as_ref(),
-                            data: &[],
+                            data: &data,
                             triggered_by: Some(&ilp_address),
                         }
                         .build()));
                     }
                     _ => {
                         error!("Got invalid settlement message from account {} that could not be parsed as a JSON object", request.from.id());
+                        let data = SettlementErrorData::new(
+                            SettlementErrorCode::NotJsonObject,
+                            "Message is not a JSON object",
+                        )
+                        .to_bytes();
                         return Box::new(err(RejectBuilder {
                             code: ErrorCode::F00_BAD_REQUEST,
                             message: b"Unable to parse message as a JSON object",
-                            data: &[],
+                            data: &data,
                             triggered_by: Some(&ilp_address),
                         }
                         .build()));
                     }
                 }
-            } else {
-                error!("Got settlement packet from account {} but there is no settlement engine url configured for it", request.from.id());
-                return Box::new(err(RejectBuilder {
-                    code: ErrorCode::F02_UNREACHABLE,
-                    message: &[],
-                    data: &[],
-                    triggered_by: Some(&ilp_address),
-                }
-                .build()));
             }
         }
+        // Peer protocol packets must never be routed onward, so anything addressed to
+        // a settlement engine that was not handled above is rejected here
+        if is_settlement_address(&request.prepare.destination()) {
+            error!("Got settlement packet from account {} but there is no settlement engine configured for it at that address", request.from.id());
+            let data = SettlementErrorData::new(
+                SettlementErrorCode::NoSettlementEngine,
+                "No settlement engine configured for this destination",
+            )
+            .to_bytes();
+            return Box::new(err(RejectBuilder {
+                code: ErrorCode::F02_UNREACHABLE,
+                message: b"No settlement engine configured",
+                data: &data,
+                triggered_by: Some(&ilp_address),
+            }
+            .build()));
+        }
         Box::new(self.next.handle_request(request))
     }
 }
+
+/// Settlement engines conventionally use addresses under `peer.settle`
+/// (for example `peer.settle.xrp-paychan`). Packets sent there that are not
+/// handled by the account's settlement engine are rejected rather than routed.
+fn is_settlement_address(destination: &Address) -> bool {
+    let mut segments = destination.segments();
+    segments.next() == Some("peer") && segments.next() == Some("settle")
+}
+
+/// Wrap the settlement engine's response in a Fulfill packet, rejecting the
+/// request instead if the response would not fit in the packet's data field.
+///
+/// Oversized responses are always rejected rather than truncated, because
+/// cutting a JSON body short would hand the peer a corrupt message.
+fn engine_response_to_fulfill(body: &[u8], ilp_address: &Address) -> Result<Fulfill, Reject> {
+    if body.len() > MAX_DATA_SIZE {
+        error!(
+            "Settlement engine response is {} bytes, which exceeds the maximum ILP packet data size of {} bytes",
+            body.len(),
+            MAX_DATA_SIZE
+        );
+        let data = SettlementErrorData::new(
+            SettlementErrorCode::EngineResponseTooLarge,
+            "Settlement engine response too large",
+        )
+        .to_bytes();
+        return Err(RejectBuilder {
+            code: ErrorCode::T00_INTERNAL_ERROR,
+            message: b"Settlement engine response too large",
+            data: &data,
+            triggered_by: Some(ilp_address),
+        }
+        .build());
+    }
+    Ok(FulfillBuilder {
+        fulfillment: &PEER_FULFILLMENT,
+        data: body,
+    }
+    .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SettlementEngineDetails;
+    use hyper::{service::service_fn_ok, Body, Request, Response, Server};
+    use interledger_packet::PrepareBuilder;
+    use interledger_service::{incoming_service_fn, Account};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+    use tokio::runtime::Runtime;
+    use url::Url;
+
+    static NEXT_SERVICE_DATA: &[u8] = b"handled by next service";
+
+    #[derive(Debug, Clone)]
+    struct TestAccount {
+        settlement_engine_url: Option<Url>,
+    }
+
+    impl TestAccount {
+        fn with_engine() -> Self {
+            TestAccount {
+                settlement_engine_url: Some(Url::parse("http://localhost:3000").unwrap()),
+            }
+        }
+
+        fn without_engine() -> Self {
+            TestAccount {
+                settlement_engine_url: None,
+            }
+        }
+    }
+
+    impl Account for TestAccount {
+        type AccountId = u64;
+        fn id(&self) -> u64 {
+            0
+        }
+    }
+
+    impl SettlementAccount for TestAccount {
+        fn settlement_engine_details(&self) -> Option<SettlementEngineDetails> {
+            self.settlement_engine_url
+                .clone()
+                .map(|url| SettlementEngineDetails {
+                    url,
+                    asset_scale: 9,
+                    ilp_address: Address::from_str("peer.settle.xrp-paychan").unwrap(),
+                })
+        }
+    }
+
+    fn test_service() -> SettlementMessageService<impl IncomingService<TestAccount>, TestAccount> {
+        SettlementMessageService::new(
+            Address::from_str("example.connector").unwrap(),
+            incoming_service_fn(|_request| {
+                Ok(FulfillBuilder {
+                    fulfillment: &[0; 32],
+                    data: NEXT_SERVICE_DATA,
+                }
+                .build())
+            }),
+        )
+    }
+
+    /// Run an HTTP server standing in for the settlement engine. It records the
+    /// Authorization header of each request and responds with the given body.
+    fn mock_engine(
+        runtime: &mut Runtime,
+        response_body: Vec<u8>,
+    ) -> (TestAccount, Arc<Mutex<Vec<Option<String>>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(move || {
+            let received = received_clone.clone();
+            let response_body = response_body.clone();
+            service_fn_ok(move |request: Request<Body>| {
+                let authorization = request
+                    .headers()
+                    .get("authorization")
+                    .map(|value| value.to_str().unwrap().to_string());
+                received.lock().unwrap().push(authorization);
+                Response::new(Body::from(response_body.clone()))
+            })
+        });
+        let account = TestAccount {
+            settlement_engine_url: Some(
+                Url::parse(&format!("http://{}", server.local_addr())).unwrap(),
+            ),
+        };
+        runtime.spawn(server.map_err(|err| panic!("Mock settlement engine error: {:?}", err)));
+        (account, received)
+    }
+
+    fn test_request(
+        from: TestAccount,
+        destination: &str,
+        data: &[u8],
+    ) -> IncomingRequest<TestAccount> {
+        IncomingRequest {
+            from,
+            prepare: PrepareBuilder {
+                destination: Address::from_str(destination).unwrap(),
+                amount: 0,
+                expires_at: SystemTime::now() + Duration::from_secs(30),
+                data,
+                execution_condition: &[0; 32],
+            }
+            .build(),
+        }
+    }
+
+    #[test]
+    fn rejects_with_structured_data_when_no_engine_configured() {
+        let reject = test_service()
+            .handle_request(test_request(
+                TestAccount::without_engine(),
+                "peer.settle.xrp-paychan",
+                b"{}",
+            ))
+            .wait()
+            .unwrap_err();
+        assert_eq!(reject.code(), ErrorCode::F02_UNREACHABLE);
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::NoSettlementEngine);
+        assert!(!data.description.is_empty());
+    }
+
+    #[test]
+    fn rejects_settlement_packets_not_for_the_accounts_engine() {
+        let reject = test_service()
+            .handle_request(test_request(
+                TestAccount::with_engine(),
+                "peer.settle.other",
+                b"{}",
+            ))
+            .wait()
+            .unwrap_err();
+        assert_eq!(reject.code(), ErrorCode::F02_UNREACHABLE);
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::NoSettlementEngine);
+    }
+
+    #[test]
+    fn passes_non_settlement_packets_to_next_service() {
+        for account in vec![TestAccount::with_engine(), TestAccount::without_engine()] {
+            let fulfill = test_service()
+                .handle_request(test_request(account, "example.other", b"{}"))
+                .wait()
+                .unwrap();
+            assert_eq!(fulfill.data(), NEXT_SERVICE_DATA);
+        }
+    }
+
+    #[test]
+    fn rejects_with_structured_data_when_message_is_not_json() {
+        let reject = test_service()
+            .handle_request(test_request(
+                TestAccount::with_engine(),
+                "peer.settle.xrp-paychan",
+                b"not json",
+            ))
+            .wait()
+            .unwrap_err();
+        assert_eq!(reject.code(), ErrorCode::F00_BAD_REQUEST);
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::InvalidJson);
+    }
+
+    #[test]
+    fn rejects_with_structured_data_when_message_is_not_an_object() {
+        let reject = test_service()
+            .handle_request(test_request(
+                TestAccount::with_engine(),
+                "peer.settle.xrp-paychan",
+                b"[1, 2, 3]",
+            ))
+            .wait()
+            .unwrap_err();
+        assert_eq!(reject.code(), ErrorCode::F00_BAD_REQUEST);
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::NotJsonObject);
+    }
+
+    #[test]
+    fn deserializes_address_match_mode_from_config_names() {
+        let modes: Vec<AddressMatchMode> =
+            serde_json::from_str(r#"["exact", "prefix", "suffix"]"#).unwrap();
+        assert_eq!(
+            modes,
+            vec![
+                AddressMatchMode::Exact,
+                AddressMatchMode::Prefix,
+                AddressMatchMode::Suffix
+            ]
+        );
+    }
+
+    #[test]
+    fn exact_mode_matches_only_equal_addresses() {
+        let engine = Address::from_str("peer.settle.xrp-paychan").unwrap();
+        let mode = AddressMatchMode::Exact;
+        assert!(mode.matches(
+            &Address::from_str("peer.settle.xrp-paychan").unwrap(),
+            &engine
+        ));
+        assert!(!mode.matches(
+            &Address::from_str("peer.settle.xrp-paychan.a").unwrap(),
+            &engine
+        ));
+        assert!(!mode.matches(&Address::from_str("peer.settle").unwrap(), &engine));
+    }
+
+    #[test]
+    fn prefix_mode_matches_whole_leading_segments() {
+        let engine = Address::from_str("peer.settle").unwrap();
+        let mode = AddressMatchMode::Prefix;
+        assert!(mode.matches(&Address::from_str("peer.settle").unwrap(), &engine));
+        assert!(mode.matches(
+            &Address::from_str("peer.settle.xrp-paychan").unwrap(),
+            &engine
+        ));
+        assert!(!mode.matches(&Address::from_str("peer.settlement").unwrap(), &engine));
+        assert!(!mode.matches(&Address::from_str("example.peer.settle").unwrap(), &engine));
+    }
+
+    #[test]
+    fn suffix_mode_matches_whole_trailing_segments() {
+        let engine = Address::from_str("settle.xrp-paychan").unwrap();
+        let mode = AddressMatchMode::Suffix;
+        assert!(mode.matches(&Address::from_str("settle.xrp-paychan").unwrap(), &engine));
+        assert!(mode.matches(
+            &Address::from_str("peer.settle.xrp-paychan").unwrap(),
+            &engine
+        ));
+        assert!(!mode.matches(
+            &Address::from_str("peer.presettle.xrp-paychan").unwrap(),
+            &engine
+        ));
+        assert!(!mode.matches(
+            &Address::from_str("peer.settle.xrp-paychan.a").unwrap(),
+            &engine
+        ));
+    }
+
+    #[test]
+    fn handles_messages_matched_by_configured_mode() {
+        let mut service = test_service();
+        service.address_match_mode(AddressMatchMode::Prefix);
+        let reject = service
+            .handle_request(test_request(
+                TestAccount::with_engine(),
+                "peer.settle.xrp-paychan.extra",
+                b"not json",
+            ))
+            .wait()
+            .unwrap_err();
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::InvalidJson);
+    }
+
+    #[test]
+    fn passes_destinations_not_matched_by_configured_mode_to_next_service() {
+        // The account's settlement engine address is peer.settle.xrp-paychan
+        let modes = vec![
+            AddressMatchMode::Exact,
+            AddressMatchMode::Prefix,
+            AddressMatchMode::Suffix,
+        ];
+        for mode in modes {
+            let mut service = test_service();
+            service.address_match_mode(mode);
+            let fulfill = service
+                .handle_request(test_request(
+                    TestAccount::with_engine(),
+                    "example.settle.xrp-paychan",
+                    b"not json",
+                ))
+                .wait()
+                .unwrap();
+            assert_eq!(fulfill.data(), NEXT_SERVICE_DATA);
+        }
+    }
+
+    #[test]
+    fn rejects_settlement_destinations_not_matched_by_configured_mode() {
+        // The account's settlement engine address is peer.settle.xrp-paychan
+        let cases = vec![
+            (AddressMatchMode::Exact, "peer.settle.xrp-paychan.extra"),
+            (AddressMatchMode::Prefix, "peer.settle.other"),
+            (AddressMatchMode::Suffix, "peer.settle.xrp-paychan.extra"),
+        ];
+        for (mode, destination) in cases {
+            let mut service = test_service();
+            service.address_match_mode(mode);
+            let reject = service
+                .handle_request(test_request(
+                    TestAccount::with_engine(),
+                    destination,
+                    b"not json",
+                ))
+                .wait()
+                .unwrap_err();
+            assert_eq!(reject.code(), ErrorCode::F02_UNREACHABLE);
+            let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+            assert_eq!(data.code, SettlementErrorCode::NoSettlementEngine);
+        }
+    }
+
+    #[test]
+    fn fulfills_with_engine_response_within_size_limit() {
+        let ilp_address = Address::from_str("example.connector").unwrap();
+        let body = vec![b'a'; MAX_DATA_SIZE];
+        let fulfill = engine_response_to_fulfill(&body, &ilp_address).unwrap();
+        assert_eq!(fulfill.data(), &body[..]);
+    }
+
+    #[test]
+    fn rejects_oversized_engine_response() {
+        let ilp_address = Address::from_str("example.connector").unwrap();
+        let body = vec![b'a'; MAX_DATA_SIZE + 1];
+        let reject = engine_response_to_fulfill(&body, &ilp_address).unwrap_err();
+        assert_eq!(reject.code(), ErrorCode::T00_INTERNAL_ERROR);
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::EngineResponseTooLarge);
+    }
+
+    #[test]
+    fn sends_auth_token_to_engine_when_configured() {
+        let mut runtime = Runtime::new().unwrap();
+        let (account, received) = mock_engine(&mut runtime, b"{}".to_vec());
+        let mut service = test_service();
+        service.auth_token("secret".to_string());
+        runtime
+            .block_on(service.handle_request(test_request(
+                account,
+                "peer.settle.xrp-paychan",
+                b"{}",
+            )))
+            .unwrap();
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![Some("Bearer secret".to_string())]
+        );
+    }
+
+    #[test]
+    fn omits_auth_token_when_not_configured() {
+        let mut runtime = Runtime::new().unwrap();
+        let (account, received) = mock_engine(&mut runtime, b"{}".to_vec());
+        runtime
+            .block_on(test_service().handle_request(test_request(
+                account,
+                "peer.settle.xrp-paychan",
+                b"{}",
+            )))
+            .unwrap();
+        assert_eq!(*received.lock().unwrap(), vec![None]);
+    }
+
+    #[test]
+    fn rejects_oversized_engine_response_through_service() {
+        let mut runtime = Runtime::new().unwrap();
+        let (account, _received) = mock_engine(&mut runtime, vec![b'a'; MAX_DATA_SIZE + 1]);
+        let reject = runtime
+            .block_on(test_service().handle_request(test_request(
+                account,
+                "peer.settle.xrp-paychan",
+                b"{}",
+            )))
+            .unwrap_err();
+        assert_eq!(reject.code(), ErrorCode::T00_INTERNAL_ERROR);
+        let data: SettlementErrorData = serde_json::from_slice(reject.data()).unwrap();
+        assert_eq!(data.code, SettlementErrorCode::EngineResponseTooLarge);
+    }
+}
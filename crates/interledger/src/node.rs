@@ -14,7 +14,7 @@ use interledger_service_util::{
     BalanceService, ExchangeRateService, ExpiryShortenerService, MaxPacketAmountService,
     RateLimitService, ValidatorService,
 };
-use interledger_settlement::SettlementMessageService;
+use interledger_settlement::{AddressMatchMode, SettlementMessageService};
 use interledger_store_redis::{Account, ConnectionInfo, IntoConnectionInfo, RedisStoreBuilder};
 use interledger_stream::StreamReceiverService;
 use ring::{digest, hmac};
@@ -96,6 +96,12 @@ pub struct InterledgerNode {
     /// Interval, defined in milliseconds, on which the node will broadcast routing
     /// information to other nodes using CCP. Defaults to 30000ms (30 seconds).
     pub route_broadcast_interval: Option<u64>,
+    /// Bearer token sent to settlement engines when forwarding settlement messages
+    /// from peers, so the engines can verify that the messages came from this node
+    pub settlement_message_auth_token: Option<String>,
+    /// How packet destinations are matched against each account's settlement engine
+    /// address: "exact", "prefix", or "suffix". Defaults to "exact".
+    pub settlement_address_match_mode: Option<AddressMatchMode>,
 }
 
 impl InterledgerNode {
@@ -117,6 +123,8 @@ impl InterledgerNode {
         let default_spsp_account = self.default_spsp_account;
         let redis_addr = self.redis_connection.addr.clone();
         let route_broadcast_interval = self.route_broadcast_interval;
+        let settlement_message_auth_token = self.settlement_message_auth_token.clone();
+        let settlement_address_match_mode = self.settlement_address_match_mode;
 
         RedisStoreBuilder::new(self.redis_connection.clone(), redis_secret)
         .connect()
@@ -191,7 +199,14 @@ impl InterledgerNode {
                                     }
                                     let incoming_service = ccp_builder.to_service();
 
-                                    let incoming_service = SettlementMessageService::new(ilp_address.clone(), incoming_service);
+                                    let mut settlement_message_service = SettlementMessageService::new(ilp_address.clone(), incoming_service);
+                                    if let Some(mode) = settlement_address_match_mode {
+                                        settlement_message_service.address_match_mode(mode);
+                                    }
+                                    if let Some(auth_token) = settlement_message_auth_token {
+                                        settlement_message_service.auth_token(auth_token);
+                                    }
+                                    let incoming_service = settlement_message_service;
                                     let incoming_service = IldcpService::new(incoming_service);
                                     let incoming_service =
                                         MaxPacketAmountService::new(incoming_service);
@@ -33,6 +33,8 @@ fn btp_end_to_end() {
         http_address: ([127, 0, 0, 1], http_port).into(),
         secret_seed: cli::random_secret(),
         route_broadcast_interval: Some(200),
+        settlement_message_auth_token: None,
+        settlement_address_match_mode: None,
     };
     let run = ok(()).and_then(move |_| {
         let spawn_connector = ok(tokio::spawn(node.serve())).and_then(move |_| {
@@ -52,6 +52,8 @@ fn three_nodes() {
         http_address: ([127, 0, 0, 1], node1_http).into(),
         secret_seed: cli::random_secret(),
         route_broadcast_interval: Some(200),
+        settlement_message_auth_token: None,
+        settlement_address_match_mode: None,
     };
     let node1_clone = node1.clone();
     runtime.spawn(
@@ -118,6 +120,8 @@ fn three_nodes() {
         http_address: ([127, 0, 0, 1], node2_http).into(),
         secret_seed: cli::random_secret(),
         route_broadcast_interval: Some(200),
+        settlement_message_auth_token: None,
+        settlement_address_match_mode: None,
     };
     runtime.spawn(
         join_all(vec![
@@ -194,6 +198,8 @@ fn three_nodes() {
         http_address: ([127, 0, 0, 1], node3_http).into(),
         secret_seed: cli::random_secret(),
         route_broadcast_interval: Some(200),
+        settlement_message_auth_token: None,
+        settlement_address_match_mode: None,
     };
     let node3_clone = node3.clone();
     runtime.spawn(